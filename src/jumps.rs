@@ -0,0 +1,209 @@
+//! A jump list, recording the selections from before each hop
+//!
+//! Each [`Buffer`] has its own list, which can be walked back and
+//! forth with [`jump_back`] and [`jump_forward`]. The recorded
+//! selections are shifted along with the edits made to the
+//! [`Buffer`], and a list is dropped when its [`Buffer`] is closed.
+use std::sync::{LazyLock, Mutex};
+
+use duat::{buffer::Change, prelude::*};
+
+/// Records the selections of a [`Buffer`], before hopping around it
+pub(crate) fn record(pa: &Pass, buffer: &Handle) {
+    let jump = Jump::new(pa, buffer);
+
+    let mut lists = JUMPS.lock().unwrap();
+    let jumps = match lists.iter().position(|jumps| jumps.buffer == *buffer) {
+        Some(i) => &mut lists[i],
+        None => {
+            lists.push(Jumps {
+                buffer: buffer.clone(),
+                list: Vec::new(),
+                current: 0,
+            });
+            lists.last_mut().unwrap()
+        }
+    };
+
+    jumps.shift(pa);
+    jumps.list.truncate(jumps.current);
+    jumps.list.push(jump);
+    jumps.current = jumps.list.len();
+}
+
+/// Drops the jump list of a closed [`Buffer`]
+pub(crate) fn forget(buffer: &Handle) {
+    let mut lists = JUMPS.lock().unwrap();
+    lists.retain(|jumps| jumps.buffer != *buffer);
+}
+
+/// Goes back to the selections from before the last hop
+///
+/// This only affects the current [`Buffer`], and can be reverted
+/// with [`jump_forward`].
+pub fn jump_back(pa: &mut Pass) {
+    let buffer = context::current_buffer(pa);
+
+    let mut lists = JUMPS.lock().unwrap();
+    let Some(jumps) = lists.iter_mut().find(|jumps| jumps.buffer == buffer) else {
+        context::info!("No previous jumps");
+        return;
+    };
+
+    if jumps.current == 0 {
+        context::info!("No previous jumps");
+        return;
+    }
+
+    jumps.shift(pa);
+
+    // Recording where we are now, so we can jump forward to it.
+    if jumps.current == jumps.list.len() {
+        jumps.list.push(Jump::new(pa, &buffer));
+    }
+
+    jumps.current -= 1;
+    let jump = jumps.list[jumps.current].clone();
+    drop(lists);
+
+    jump.restore(pa, &buffer);
+}
+
+/// Goes forward to the selections from before [`jump_back`]
+///
+/// This only affects the current [`Buffer`].
+pub fn jump_forward(pa: &mut Pass) {
+    let buffer = context::current_buffer(pa);
+
+    let mut lists = JUMPS.lock().unwrap();
+    let Some(jumps) = lists.iter_mut().find(|jumps| jumps.buffer == buffer) else {
+        context::info!("No next jumps");
+        return;
+    };
+
+    if jumps.current + 1 >= jumps.list.len() {
+        context::info!("No next jumps");
+        return;
+    }
+
+    jumps.shift(pa);
+
+    jumps.current += 1;
+    let jump = jumps.list[jumps.current].clone();
+    drop(lists);
+
+    jump.restore(pa, &buffer);
+}
+
+/// The jump list of a single [`Buffer`]
+struct Jumps {
+    buffer: Handle,
+    list: Vec<Jump>,
+    current: usize,
+}
+
+impl Jumps {
+    /// Shifts every [`Jump`] by the edits made since the last shift
+    ///
+    /// The first call for a [`Buffer`] only starts keeping track of
+    /// its edits.
+    fn shift(&mut self, pa: &Pass) {
+        let moment = self.buffer.read(pa).moment_for(*JUMPS_NS);
+        for change in moment.iter() {
+            for jump in self.list.iter_mut() {
+                jump.shift(&change);
+            }
+        }
+    }
+}
+
+/// The selections of a [`Buffer`] at some point
+///
+/// Each selection is stored as the byte of its anchor, if it has
+/// one, and the byte of its caret.
+#[derive(Clone)]
+struct Jump {
+    main: (Option<usize>, usize),
+    others: Vec<(Option<usize>, usize)>,
+}
+
+impl Jump {
+    /// Records the current selections of a [`Buffer`]
+    fn new(pa: &Pass, buffer: &Handle) -> Self {
+        let mut main = (None, 0);
+        let mut others = Vec::new();
+
+        for (selection, is_main) in buffer.read(pa).selections().iter() {
+            let anchor = selection.anchor().map(|anchor| anchor.byte());
+            let caret = selection.caret().byte();
+            if is_main {
+                main = (anchor, caret);
+            } else {
+                others.push((anchor, caret));
+            }
+        }
+
+        Self { main, others }
+    }
+
+    /// Shifts every byte by a [`Change`] to the [`Text`]
+    ///
+    /// Bytes within the removed part of the [`Text`] are moved to the
+    /// start of the [`Change`].
+    fn shift(&mut self, change: &Change) {
+        let (start, taken_end) = (change.start().byte(), change.taken_end().byte());
+        let added_end = change.added_end().byte();
+
+        let shift = |byte: &mut usize| {
+            if *byte >= taken_end {
+                *byte = *byte + added_end - taken_end;
+            } else if *byte > start {
+                *byte = start;
+            }
+        };
+
+        for (anchor, caret) in std::iter::once(&mut self.main).chain(self.others.iter_mut()) {
+            if let Some(anchor) = anchor {
+                shift(anchor);
+            }
+            shift(caret);
+        }
+    }
+
+    /// Restores the selections on a [`Buffer`]
+    ///
+    /// Every byte is still clamped to the [`Text`] and moved to the
+    /// start of its character, in case a [`Change`] was missed.
+    fn restore(&self, pa: &mut Pass, buffer: &Handle) {
+        let text = buffer.read(pa).text();
+        let clamp_byte = |byte: usize| text.point_at_byte(byte.min(text.len())).byte();
+        let clamp =
+            |(anchor, caret): (Option<usize>, usize)| (anchor.map(clamp_byte), clamp_byte(caret));
+
+        let (main_anchor, main_caret) = clamp(self.main);
+        let others: Vec<_> = self.others.iter().map(|&bytes| clamp(bytes)).collect();
+
+        buffer.write(pa).remove_extra_selections();
+        buffer.edit_main(pa, |mut e| {
+            for (anchor, caret) in others {
+                let mut c = e.copy();
+                c.unset_anchor();
+                if let Some(anchor) = anchor {
+                    c.move_to(anchor);
+                    c.set_anchor();
+                }
+                c.move_to(caret);
+            }
+
+            e.unset_anchor();
+            if let Some(anchor) = main_anchor {
+                e.move_to(anchor);
+                e.set_anchor();
+            }
+            e.move_to(main_caret);
+        });
+    }
+}
+
+static JUMPS: Mutex<Vec<Jumps>> = Mutex::new(Vec::new());
+static JUMPS_NS: LazyLock<Ns> = Ns::new_lazy();
@@ -0,0 +1,305 @@
+//! The labels that are handed out to targets
+//!
+//! Labels are stored in a trie, which is built once when entering
+//! the [`Hopper`] or [`WindowPicker`] [`Mode`]s, and is then walked
+//! one key at a time.
+//!
+//! [`Hopper`]: crate::Hopper
+//! [`WindowPicker`]: crate::WindowPicker
+//! [`Mode`]: duat::mode::Mode
+use std::collections::VecDeque;
+
+use duat::prelude::*;
+
+use crate::same_key;
+
+/// The index of the root of the trie
+const ROOT: usize = 0;
+
+/// A trie of labels, each one pointing to a target
+///
+/// It also keeps track of the nodes reached by the characters typed
+/// so far.
+#[derive(Clone)]
+pub struct Labels {
+    nodes: Vec<Node>,
+    seqs: Vec<String>,
+    path: Vec<usize>,
+}
+
+impl Labels {
+    /// Returns the labels for `len` targets
+    pub fn new(len: usize, alphabet: &[char]) -> Self {
+        let mut seqs = key_seqs(len, alphabet);
+        seqs.truncate(len);
+
+        let mut nodes = vec![Node::default()];
+
+        for (target, seq) in seqs.iter().enumerate() {
+            let mut node = ROOT;
+            nodes[ROOT].targets.push(target);
+
+            for char in seq.chars() {
+                node = match nodes[node].children.iter().find(|(c, _)| *c == char) {
+                    Some(&(_, child)) => child,
+                    None => {
+                        nodes.push(Node::default());
+                        let child = nodes.len() - 1;
+                        nodes[node].children.push((char, child));
+                        child
+                    }
+                };
+                nodes[node].targets.push(target);
+            }
+
+            nodes[node].target = Some(target);
+        }
+
+        Self { nodes, seqs, path: Vec::new() }
+    }
+
+    /// Types a character of a label
+    pub fn type_char(&mut self, char: char) -> Typed {
+        let node = self.node();
+        let Some(child) = self.child(node, char) else {
+            return Typed::Invalid;
+        };
+
+        if let Some(target) = self.target(child) {
+            return Typed::Target(target);
+        }
+
+        let siblings = self.children(node).filter(|sibling| *sibling != child);
+        let dropped = siblings.flat_map(|sibling| self.node_targets(sibling));
+        let dropped = dropped.copied().collect();
+
+        self.path.push(child);
+        Typed::Prefix(dropped)
+    }
+
+    /// Undoes the last typed character, returning `false` if there
+    /// was none
+    pub fn backspace(&mut self) -> bool {
+        self.path.pop().is_some()
+    }
+
+    /// Undoes every typed character
+    pub fn reset(&mut self) {
+        self.path.clear();
+    }
+
+    /// Every target whose label starts with the typed characters
+    pub fn targets(&self) -> &[usize] {
+        self.node_targets(self.node())
+    }
+
+    /// The [`Overlay`]s of every target in [`Labels::targets`]
+    ///
+    /// The characters that have already been typed are left out of
+    /// the labels.
+    pub fn overlays(&self) -> impl Iterator<Item = (usize, Overlay)> {
+        let targets = self.targets().iter();
+        targets.map(|&target| (target, label_overlay(self.seq(target))))
+    }
+
+    /// The label of a target, without the typed characters
+    pub fn seq(&self, target: usize) -> &str {
+        let seq = &self.seqs[target];
+        let depth = self.path.len();
+        seq.char_indices().nth(depth).map_or("", |(i, _)| &seq[i..])
+    }
+
+    /// The node reached by the typed characters
+    fn node(&self) -> usize {
+        self.path.last().copied().unwrap_or(ROOT)
+    }
+
+    /// The node reached by typing `char` on `node`
+    fn child(&self, node: usize, char: char) -> Option<usize> {
+        let children = &self.nodes[node].children;
+        children
+            .iter()
+            .find_map(|&(c, child)| (c == char).then_some(child))
+    }
+
+    /// The children of a node
+    fn children(&self, node: usize) -> impl Iterator<Item = usize> {
+        self.nodes[node].children.iter().map(|&(_, child)| child)
+    }
+
+    /// The target of a node, if its label is complete
+    fn target(&self, node: usize) -> Option<usize> {
+        self.nodes[node].target
+    }
+
+    /// Every target whose label goes through `node`
+    fn node_targets(&self, node: usize) -> &[usize] {
+        &self.nodes[node].targets
+    }
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node::default()],
+            seqs: Vec::new(),
+            path: Vec::new(),
+        }
+    }
+}
+
+/// The result of typing a character with [`Labels::type_char`]
+pub enum Typed {
+    /// No label continues with the character
+    Invalid,
+    /// A label was completed, picking its target
+    Target(usize),
+    /// Labels were narrowed down, dropping these targets
+    Prefix(Vec<usize>),
+}
+
+/// Whether a [`KeyEvent`] is one of the `cancel_keys`
+pub fn is_cancel(cancel_keys: &[KeyEvent], key_event: KeyEvent) -> bool {
+    cancel_keys.iter().any(|key| same_key(*key, key_event))
+}
+
+/// A node in the trie
+#[derive(Default, Clone)]
+struct Node {
+    children: Vec<(char, usize)>,
+    target: Option<usize>,
+    targets: Vec<usize>,
+}
+
+/// Returns at least `len` labels, with the shortest ones first
+fn key_seqs(len: usize, alphabet: &[char]) -> Vec<String> {
+    let mut seqs: VecDeque<String> = alphabet.iter().map(char::to_string).collect();
+
+    // Splitting the first label makes room for alphabet.len() - 1 more
+    // labels, while keeping the shortest ones at the front.
+    while seqs.len() < len {
+        let prefix = seqs.pop_front().unwrap();
+        seqs.extend(alphabet.iter().map(|char| format!("{prefix}{char}")));
+    }
+
+    seqs.into()
+}
+
+/// The [`Overlay`] that shows a label on screen
+fn label_overlay(seq: &str) -> Overlay {
+    let mut chars = seq.chars();
+    let first = chars.next().unwrap();
+    let rest = chars.as_str();
+
+    if rest.is_empty() {
+        Overlay::new(txt!("[hop.one_char:240]{first}"))
+    } else {
+        Overlay::new(txt!("[hop.char1:240]{first}[hop.char2:240]{rest}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(len: usize, alphabet: &[char]) -> Labels {
+        let labels = Labels::new(len, alphabet);
+        assert_eq!(labels.seqs.len(), len);
+        assert_eq!(labels.targets().len(), len);
+
+        for (i, seq) in labels.seqs.iter().enumerate() {
+            assert!(!seq.is_empty());
+            assert!(seq.chars().all(|char| alphabet.contains(&char)));
+
+            for (j, other) in labels.seqs.iter().enumerate() {
+                if i != j {
+                    assert_ne!(seq, other, "label {seq:?} is repeated");
+                    let prefixed = other.starts_with(seq.as_str());
+                    assert!(!prefixed, "{seq:?} prefixes {other:?}");
+                }
+            }
+
+            let mut node = ROOT;
+            for char in seq.chars() {
+                assert!(labels.node_targets(node).contains(&i));
+                node = labels.child(node, char).unwrap();
+            }
+            assert_eq!(labels.target(node), Some(i));
+            assert_eq!(labels.children(node).count(), 0);
+        }
+
+        labels
+    }
+
+    /// Types every character of a label, returning what the last one
+    /// did
+    fn type_seq(labels: &mut Labels, seq: &str) -> Typed {
+        let mut typed = Typed::Invalid;
+        for char in seq.chars() {
+            typed = labels.type_char(char);
+        }
+        typed
+    }
+
+    fn max_len(labels: &Labels) -> usize {
+        let lens = labels.seqs.iter().map(|seq| seq.chars().count());
+        lens.max().unwrap_or(0)
+    }
+
+    #[test]
+    fn default_alphabet() {
+        let letters: Vec<char> = crate::LETTERS.chars().collect();
+
+        assert_eq!(max_len(&check(0, &letters)), 0);
+        assert_eq!(max_len(&check(26, &letters)), 1);
+        assert_eq!(max_len(&check(27, &letters)), 2);
+        // Since no label prefixes another, 26 * 26 is the most that
+        // fit in two characters.
+        assert_eq!(max_len(&check(676, &letters)), 2);
+        assert_eq!(max_len(&check(702, &letters)), 3);
+        assert_eq!(max_len(&check(703, &letters)), 3);
+    }
+
+    #[test]
+    fn two_char_alphabet() {
+        let alphabet = ['a', 'b'];
+
+        assert_eq!(max_len(&check(0, &alphabet)), 0);
+        assert_eq!(max_len(&check(26, &alphabet)), 5);
+        assert_eq!(max_len(&check(27, &alphabet)), 5);
+        assert_eq!(max_len(&check(702, &alphabet)), 10);
+        assert_eq!(max_len(&check(703, &alphabet)), 10);
+    }
+
+    #[test]
+    fn shortest_labels_first() {
+        let letters: Vec<char> = crate::LETTERS.chars().collect();
+        let labels = check(27, &letters);
+
+        assert_eq!(labels.seqs[0], "b");
+        assert_eq!(labels.seqs[24], "z");
+        assert_eq!(labels.seqs[25], "aa");
+        assert_eq!(labels.seqs[26], "ab");
+    }
+
+    #[test]
+    fn walking() {
+        let letters: Vec<char> = crate::LETTERS.chars().collect();
+        let mut labels = check(27, &letters);
+
+        let Typed::Prefix(dropped) = labels.type_char('a') else {
+            panic!("\"a\" should be a prefix");
+        };
+        assert_eq!(dropped.len(), 25);
+        assert_eq!(labels.targets(), [25, 26]);
+        assert_eq!(labels.seq(26), "b");
+        assert!(matches!(labels.type_char('c'), Typed::Invalid));
+
+        assert!(labels.backspace());
+        assert!(!labels.backspace());
+        assert!(matches!(type_seq(&mut labels, "ab"), Typed::Target(26)));
+
+        labels.reset();
+        assert!(matches!(type_seq(&mut labels, "z"), Typed::Target(24)));
+    }
+}
@@ -1,9 +1,11 @@
 //! A duat [`Mode`] to quickly move around the screen, inspired by
 //! [`hop.nvim`].
 //!
-//! This plugin will highlight every word (or line, or a custom regex)
-//! in the screen, and let you jump to it with at most 2 keypresses,
-//! selecting the matched sequence.
+//! This plugin will highlight every word (or line, or a custom
+//! [`HopTarget`]) in the screen, and let you jump to it with a few
+//! keypresses, selecting the matched sequence. Labels start out with
+//! one character, and get longer as the number of targets on screen
+//! grows.
 //!
 //! # Installation
 //!
@@ -30,7 +32,7 @@
 //! use duat::prelude::*;
 //!
 //! fn setup() {
-//!     plug(duat_hop::Hop);
+//!     plug(duat_hop::Hop::new());
 //! }
 //! ```
 //!
@@ -38,6 +40,76 @@
 //! in the [`User`] mode, while the `l` key will map onto
 //! [`Hopper::line`] in the same mode.
 //!
+//! Before every hop, the selections are recorded in a jump list. The
+//! `o` and `i` keys in the [`User`] mode will map onto [`jump_back`]
+//! and [`jump_forward`], letting you undo a hop without undoing any
+//! edits. The `.` key will map onto [`repeat_hop`], which hops again
+//! with the last [`Hopper`] used. The `W` key will map onto a
+//! [`WindowPicker`], which labels every visible window and focuses
+//! the one whose label is typed.
+//!
+//! If you want these keys in another [`Mode`], or don't want them at
+//! all, you can change that, and map your own [`Hopper`]s, or
+//! anything else that implements [`MapsToHop`]:
+//!
+//! ```rust
+//! setup_duat!(setup);
+//! use duat::prelude::*;
+//! use duat_hop::{Direction, Hop, Hopper, WindowPicker, jump_back};
+//!
+//! fn setup() {
+//!     let before = Hopper::word().with_direction(Direction::Before);
+//!     let after = Hopper::word().with_direction(Direction::After);
+//!
+//!     plug(
+//!         Hop::new()
+//!             .without_default_maps()
+//!             .map::<mode::User>("f", Hopper::char(), txt!("[mode]Hop[] to a [a]char"))
+//!             .map::<mode::User>("/", Hopper::pattern(), txt!("[mode]Hop[] to a [a]pattern"))
+//!             .map::<mode::User>("b", before, txt!("[mode]Hop[] to a [a]word[] before"))
+//!             .map::<mode::User>("a", after, txt!("[mode]Hop[] to a [a]word[] after"))
+//!             .map::<mode::User>("u", jump_back, txt!("Jump back from a [mode]hop"))
+//!             .map::<mode::User>("p", WindowPicker::new(), txt!("Pick a [a]window")),
+//!     );
+//! }
+//! ```
+//!
+//! You can also change or remove the keys of specific default maps,
+//! through [`Hop::with_default_keys`].
+//!
+//! # Labels
+//!
+//! By default, labels are made out of the lowercase letters of the
+//! alphabet, in alphabetical order. If you want the easiest keys to
+//! reach to be handed out first, you can change that:
+//!
+//! ```rust
+//! setup_duat!(setup);
+//! use duat::prelude::*;
+//!
+//! fn setup() {
+//!     plug(duat_hop::Hop::new().with_alphabet("asdfghjkl;"));
+//! }
+//! ```
+//!
+//! This can also be overridden for a specific [`Hopper`], through
+//! [`Hopper::with_alphabet`].
+//!
+//! By default, labels are handed out in the order that targets show
+//! up on screen. You can instead hand out the shortest labels to the
+//! targets closest to the main cursor, like `hop.nvim` does, with an
+//! [`Order`]:
+//!
+//! ```rust
+//! setup_duat!(setup);
+//! use duat::prelude::*;
+//! use duat_hop::Order;
+//!
+//! fn setup() {
+//!     plug(duat_hop::Hop::new().with_order(Order::Lines));
+//! }
+//! ```
+//!
 //! # Forms
 //!
 //! When plugging [`Hop`] will set the `"hop"` [`Form`] to
@@ -45,10 +117,18 @@
 //! [`Form`]s:
 //!
 //! - `"hop.one_char"` will be used on labels with just one character.
-//! - `"hop.char1"` will be used on the first character of two
-//!   character labels.
-//! - `"hop.char2"` will be used on the second character of two
-//!   character labels. By default, this form inherits `"hop.char1"`.
+//! - `"hop.char1"` will be used on the first character of longer
+//!   labels.
+//! - `"hop.char2"` will be used on the remaining characters of longer
+//!   labels. By default, this form inherits `"hop.char1"`.
+//! - `"hop.match"` will be used on the matches of what was typed in
+//!   [`Hopper::char`], [`Hopper::char2`] and [`Hopper::pattern`]. By
+//!   default, this form inherits `"search"`.
+//! - `"hop.picked"` will be used on targets picked with
+//!   [`Action::MultiPick`] and [`Action::Swap`]. By default, this
+//!   form inherits `"selection.extra"`.
+//! - `"hop.window"` will be used on the labels of the
+//!   [`WindowPicker`].
 //!
 //! Which you can modify via [`form::set`]:
 //!
@@ -57,7 +137,7 @@
 //! use duat::prelude::*;
 //!
 //! fn setup() {
-//!     plug(duat_hop::Hop);
+//!     plug(duat_hop::Hop::new());
 //!
 //!     form::set("hop.one_char", Form::new().red().underlined());
 //!     form::set("hop.char1", Form::mimic("hop.one_char"));
@@ -65,82 +145,328 @@
 //! }
 //! ```
 //!
+//! # Targets
+//!
+//! Besides words, lines and regexes, you can hop to anything that
+//! implements [`HopTarget`], including functions:
+//!
+//! ```rust
+//! setup_duat!(setup);
+//! use std::ops::Range;
+//!
+//! use duat::prelude::*;
+//! use duat_hop::Hopper;
+//!
+//! fn setup() {
+//!     // Hops to every empty line on screen.
+//!     let empty_lines = |text: &Text, range: Range<usize>| -> Vec<Range<usize>> {
+//!         let lines = text.search("\n\n").range(range);
+//!         lines.map(|r| r.start + 1..r.end).collect()
+//!     };
+//!
+//!     mode::map::<mode::User>("e", move |pa: &mut Pass| {
+//!         mode::set(pa, Hopper::with_target(empty_lines))
+//!     });
+//! }
+//! ```
+//!
 //! [`Mode`]: duat::mode::Mode
 //! [`hop.nvim`]: https://github.com/smoka7/hop.nvim
 //! [`User`]: duat::mode::User
 //! [`Form`]: duat::form::Form
 //! [`form::set`]: duat::form::set
-use std::{ops::Range, sync::LazyLock};
+use std::{
+    ops::Range,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use duat::{
+    Plugin, Plugins,
+    prelude::*,
+    text::{Point, Text},
+};
+
+use crate::labels::{Labels, Typed, is_cancel};
+
+mod jumps;
+mod labels;
+mod picker;
+mod target;
 
-use duat::{Plugin, Plugins, prelude::*};
+pub use crate::{
+    jumps::{jump_back, jump_forward},
+    picker::WindowPicker,
+    target::{HopTarget, RegexTarget},
+};
 
 /// The [`Plugin`] for the [`Hopper`] [`Mode`].
-#[derive(Default)]
-pub struct Hop;
+pub struct Hop {
+    alphabet: Option<Arc<[char]>>,
+    order: Option<Order>,
+    cancel_keys: Option<Arc<[KeyEvent]>>,
+    cancel_info: bool,
+    return_hooks: Vec<fn()>,
+    default_maps: Option<fn(DefaultKeys)>,
+    default_keys: DefaultKeys,
+    maps: Vec<Box<dyn FnOnce()>>,
+}
 
-impl Plugin for Hop {
-    fn plug(self, opts: &mut Opts, _: &Plugins) {
-        mode::map::<mode::User>("w", |pa: &mut Pass| mode::set(pa, Hopper::word()))
-            .doc(txt!("[mode]Hop[] to a [a]word"));
-        mode::map::<mode::User>("l", |pa: &mut Pass| mode::set(pa, Hopper::line()))
-            .doc(txt!("[mode]Hop[] to a [a]line"));
+impl Hop {
+    /// Returns a new instance of the [`Hop`] [`Plugin`]
+    pub fn new() -> Self {
+        Self {
+            alphabet: None,
+            order: None,
+            cancel_keys: None,
+            cancel_info: false,
+            return_hooks: vec![
+                add_return_hook::<mode::Normal>,
+                add_return_hook::<mode::Insert>,
+            ],
+            default_maps: Some(map_defaults::<mode::User>),
+            default_keys: DefaultKeys::default(),
+            maps: Vec::new(),
+        }
+    }
 
-        opts.whichkey.always_show::<Hopper>();
+    /// Maps the default keys in the [`Mode`] `M`, instead of
+    /// [`User`]
+    ///
+    /// [`User`]: mode::User
+    pub fn default_maps_in<M: Mode>(self) -> Self {
+        Self {
+            default_maps: Some(map_defaults::<M>),
+            ..self
+        }
+    }
 
-        form::set_weak("hop", Form::mimic("accent.info"));
-        form::set_weak("hop.char2", Form::mimic("hop.char1"));
+    /// Doesn't map the default keys
+    ///
+    /// You can still map [`Hopper`]s, [`jump_back`],
+    /// [`jump_forward`], [`repeat_hop`] and the [`WindowPicker`]
+    /// through [`Hop::map`].
+    pub fn without_default_maps(self) -> Self {
+        Self { default_maps: None, ..self }
+    }
 
-        let cloak_ns = Ns::new();
+    /// Changes the keys of the default maps
+    ///
+    /// Each default map can be moved to other keys, or left out by
+    /// setting its keys to [`None`]:
+    ///
+    /// ```rust
+    /// setup_duat!(setup);
+    /// use duat::prelude::*;
+    /// use duat_hop::{DefaultKeys, Hop};
+    ///
+    /// fn setup() {
+    ///     plug(Hop::new().with_default_keys(DefaultKeys {
+    ///         word: Some("f"),
+    ///         line: None,
+    ///         ..DefaultKeys::default()
+    ///     }));
+    /// }
+    /// ```
+    pub fn with_default_keys(self, default_keys: DefaultKeys) -> Self {
+        Self { default_keys, ..self }
+    }
 
-        hook::add::<ModeSwitched>(move |pa, mut switch| {
-            if let Some(hop) = switch.new.get_as::<Hopper>() {
-                let buffer = context::current_buffer(pa);
+    /// Maps keys in the [`Mode`] `M` to a [`Hopper`], or anything
+    /// else that implements [`MapsToHop`]
+    ///
+    /// The `doc` will be shown in the which key widget.
+    pub fn map<M: Mode>(mut self, keys: &str, mut hop: impl MapsToHop, doc: Text) -> Self {
+        let keys = keys.to_string();
+        self.maps.push(Box::new(move || {
+            mode::map::<M>(&keys, move |pa: &mut Pass| hop.run(pa)).doc(doc);
+        }));
+        self
+    }
+
+    /// Changes the characters used on labels
+    ///
+    /// The characters are handed out in order, so the ones that are
+    /// easiest to reach should come first. Repeated characters are
+    /// ignored.
+    ///
+    /// This will be the default for every [`Hopper`], unless
+    /// [`Hopper::with_alphabet`] is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are less than two distinct characters.
+    pub fn with_alphabet(self, alphabet: &str) -> Self {
+        Self {
+            alphabet: Some(new_alphabet(alphabet)),
+            ..self
+        }
+    }
+
+    /// Changes the [`Order`] in which labels are handed out
+    ///
+    /// This will be the default for every [`Hopper`], unless
+    /// [`Hopper::with_order`] is used.
+    pub fn with_order(self, order: Order) -> Self {
+        Self { order: Some(order), ..self }
+    }
+
+    /// Changes the keys that cancel hopping
+    ///
+    /// By default, only [`KeyCode::Esc`] cancels hopping. Cancelling
+    /// doesn't emit an error, unlike other invalid keys.
+    ///
+    /// A warning is shown if a cancel key is also a character of the
+    /// alphabet, since that character couldn't be typed in labels.
+    ///
+    /// This will be the default for every [`Hopper`], unless
+    /// [`Hopper::with_cancel_keys`] is used.
+    pub fn with_cancel_keys(self, keys: impl IntoIterator<Item = KeyEvent>) -> Self {
+        Self {
+            cancel_keys: Some(keys.into_iter().collect()),
+            ..self
+        }
+    }
+
+    /// Shows an informational message when hopping is cancelled
+    pub fn with_cancel_info(self) -> Self {
+        Self { cancel_info: true, ..self }
+    }
 
-                let (buf, area) = buffer.write_with_area(pa);
+    /// Returns to the [`Mode`] `M` after hopping, if hopping started
+    /// from it
+    ///
+    /// Since an arbitrary [`Mode`] can't be copied when switching to
+    /// a [`Hopper`], the ones that should be returned to need to be
+    /// registered this way. From any other [`Mode`], hopping will end
+    /// on the default [`Mode`] for [`Buffer`]s. duat's [`Normal`] and
+    /// [`Insert`] modes are registered by default.
+    ///
+    /// This also applies to the [`WindowPicker`]. When hopping or
+    /// picking focuses another [`Buffer`], `M` is returned to on
+    /// that [`Buffer`].
+    ///
+    /// ```rust
+    /// setup_duat!(setup);
+    /// use duat::prelude::*;
+    ///
+    /// fn setup() {
+    ///     // Hopping from the User mode will return to it.
+    ///     plug(duat_hop::Hop::new().return_to::<mode::User>());
+    /// }
+    /// ```
+    ///
+    /// [`Normal`]: mode::Normal
+    /// [`Insert`]: mode::Insert
+    pub fn return_to<M: Mode + Clone>(mut self) -> Self {
+        self.return_hooks.push(add_return_hook::<M>);
+        self
+    }
+}
+
+impl Plugin for Hop {
+    fn plug(self, opts: &mut Opts, _: &Plugins) {
+        let mut defaults = DEFAULTS.lock().unwrap();
+        if let Some(alphabet) = self.alphabet {
+            defaults.alphabet = alphabet;
+        }
+        if let Some(order) = self.order {
+            defaults.order = order;
+        }
+        if let Some(cancel_keys) = self.cancel_keys {
+            defaults.cancel_keys = cancel_keys;
+        }
+        defaults.cancel_info = self.cancel_info;
+        check_cancel_keys(&defaults.alphabet, &defaults.cancel_keys);
+        drop(defaults);
 
-                let opts = buf.print_opts();
-                let mut text = buf.text_mut();
+        for add_return_hook in self.return_hooks {
+            add_return_hook();
+        }
 
-                let id = form::id_of!("cloak");
-                text.insert_tag(cloak_ns, .., id.to_tag(239));
+        if let Some(map_defaults) = self.default_maps {
+            map_defaults(self.default_keys);
+        }
+        for map in self.maps {
+            map();
+        }
 
-                let start = area.start_points(&text, opts).real;
-                let end = area.end_points(&text, opts).real;
+        opts.whichkey.always_show::<Hopper>();
+        opts.whichkey.always_show::<WindowPicker>();
 
-                hop.ranges = text.search(hop.regex).range(start..end).collect();
+        form::set_weak("hop", Form::mimic("accent.info"));
+        form::set_weak("hop.char2", Form::mimic("hop.char1"));
+        form::set_weak("hop.match", Form::mimic("search"));
+        form::set_weak("hop.picked", Form::mimic("selection.extra"));
 
-                let seqs = key_seqs(hop.ranges.len());
+        hook::add::<BufferClosed>(|_, buffer| jumps::forget(buffer));
 
-                for (seq, r) in seqs.iter().zip(&hop.ranges) {
-                    let ghost = if seq.len() == 1 {
-                        Overlay::new(txt!("[hop.one_char:240]{seq}"))
-                    } else {
-                        let mut chars = seq.chars();
-                        Overlay::new(txt!(
-                            "[hop.char1:240]{}[hop.char2:240]{}",
-                            chars.next().unwrap(),
-                            chars.next().unwrap()
-                        ))
-                    };
+        hook::add::<ModeSwitched>(|pa, mut switch| {
+            if let Some(hop) = switch.new.get_as::<Hopper>() {
+                let overrides = hop.alphabet.is_some() || hop.cancel_keys.is_some();
+                let defaults = DEFAULTS.lock().unwrap();
+                let alphabet = hop
+                    .alphabet
+                    .get_or_insert_with(|| defaults.alphabet.clone());
+                hop.order.get_or_insert_with(|| defaults.order.clone());
+                let cancel_keys = hop
+                    .cancel_keys
+                    .get_or_insert_with(|| defaults.cancel_keys.clone());
+                drop(defaults);
 
-                    text.insert_tag(*NS, r.start, ghost);
+                // The defaults were already checked when plugging.
+                if overrides {
+                    check_cancel_keys(alphabet, cancel_keys);
                 }
+
+                *LAST.lock().unwrap() = Some(Hopper { return_to: None, ..hop.clone() });
+
+                hop.find_targets(pa);
             } else if switch.old.is::<Hopper>() {
-                let buffer = context::current_buffer(pa);
+                clear_targets(pa);
+            }
 
-                let mut text = buffer.text_mut(pa);
-                text.remove_tags(*NS, ..);
-                text.remove_tags(cloak_ns, ..);
+            if let Some(picker) = switch.new.get_as::<WindowPicker>() {
+                picker.label_buffers(pa);
+            } else if switch.old.is::<WindowPicker>() {
+                picker::clear_labels(pa);
             }
         });
     }
 }
 
+impl Default for Hop {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [`Mode`] to hop around the screen by typing short labels
+///
+/// # Returning to the previous [`Mode`]
+///
+/// After hopping, a [`Hopper`] only returns to the [`Mode`] it was
+/// entered from if that [`Mode`] was registered with
+/// [`Hop::return_to`]. duat's [`Normal`] and [`Insert`] modes are
+/// registered by default. From any other [`Mode`], hopping ends on
+/// the default [`Mode`] for [`Buffer`]s.
+///
+/// [`Normal`]: mode::Normal
+/// [`Insert`]: mode::Insert
 #[derive(Clone)]
 pub struct Hopper {
-    regex: &'static str,
-    ranges: Vec<Range<usize>>,
-    seq: String,
+    source: Source,
+    alphabet: Option<Arc<[char]>>,
+    order: Option<Order>,
+    direction: Direction,
+    action: Action,
+    landing: Landing,
+    cancel_keys: Option<Arc<[KeyEvent]>>,
+    confirm_key: KeyEvent,
+    return_to: Option<ReturnTo>,
+    across_windows: bool,
+    targets: Vec<Target>,
+    labels: Labels,
+    picked: Vec<usize>,
 }
 
 impl Hopper {
@@ -148,74 +474,963 @@ impl Hopper {
     /// default
     pub fn word() -> Self {
         Self {
-            regex: "[^\n\\s]+",
-            ranges: Vec::new(),
-            seq: String::new(),
+            source: Source::Target(Arc::new(RegexTarget("[^\n\\s]+".into()))),
+            alphabet: None,
+            order: None,
+            direction: Direction::Both,
+            action: Action::Move,
+            landing: Landing::Whole,
+            cancel_keys: None,
+            confirm_key: KeyEvent::from(KeyCode::Enter),
+            return_to: None,
+            across_windows: false,
+            targets: Vec::new(),
+            labels: Labels::default(),
+            picked: Vec::new(),
         }
     }
 
     /// Changes this [`Mode`] to move by line, not by word
     pub fn line() -> Self {
-        Self { regex: "[^\n\\s][^\n]+", ..Self::word() }
+        let line = RegexTarget("[^\n\\s][^\n]+".into());
+        Self {
+            source: Source::Target(Arc::new(line)),
+            ..Self::word()
+        }
     }
 
     /// Use a custom regex instead of the word or line regexes
-    pub fn with_regex(regex: &'static str) -> Self {
-        Self { regex, ..Self::word() }
+    ///
+    /// The regex can be built at runtime:
+    ///
+    /// ```rust
+    /// # use duat_hop::Hopper;
+    /// let word = "hop";
+    /// let hopper = Hopper::with_regex(format!("\\b{word}\\b")).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the regex is not valid.
+    pub fn with_regex(regex: impl Into<Arc<str>>) -> Result<Self, Box<regex_syntax::Error>> {
+        Ok(Self::with_target(RegexTarget::new(regex)?))
+    }
+
+    /// Use a custom [`HopTarget`] to find targets on screen
+    pub fn with_target(target: impl HopTarget) -> Self {
+        Self {
+            source: Source::Target(Arc::new(target)),
+            ..Self::word()
+        }
+    }
+
+    /// Hops to a character, typed after entering this [`Mode`]
+    ///
+    /// Once the character is typed, every occurrence of it on screen
+    /// will be highlighted and labeled, like `hop.nvim`'s
+    /// `HopChar1`.
+    pub fn char() -> Self {
+        Self {
+            source: Source::Chars { len: 1, typed: String::new() },
+            ..Self::word()
+        }
+    }
+
+    /// Hops to a pair of characters, typed after entering this
+    /// [`Mode`]
+    ///
+    /// Once both characters are typed, every occurrence of them on
+    /// screen will be highlighted and labeled, like `hop.nvim`'s
+    /// `HopChar2`.
+    pub fn char2() -> Self {
+        Self {
+            source: Source::Chars { len: 2, typed: String::new() },
+            ..Self::word()
+        }
+    }
+
+    /// Hops to a regex pattern, typed after entering this [`Mode`]
+    ///
+    /// As the pattern is typed, its matches on screen are highlighted
+    /// and labeled. While the pattern is not a valid regex, like
+    /// right after typing an opening `(`, the previous matches are
+    /// kept. Once the confirm key ([`KeyCode::Enter`] by default) is
+    /// pressed, typing will select labels instead.
+    pub fn pattern() -> Self {
+        Self {
+            source: Source::Pattern { typed: String::new(), confirmed: false },
+            ..Self::word()
+        }
+    }
+
+    /// Changes the characters used on labels for this [`Hopper`]
+    ///
+    /// This overrides the alphabet set by [`Hop::with_alphabet`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are less than two distinct characters.
+    pub fn with_alphabet(self, alphabet: &str) -> Self {
+        Self {
+            alphabet: Some(new_alphabet(alphabet)),
+            ..self
+        }
+    }
+
+    /// Changes the [`Order`] in which labels are handed out
+    ///
+    /// This overrides the [`Order`] set by [`Hop::with_order`].
+    pub fn with_order(self, order: Order) -> Self {
+        Self { order: Some(order), ..self }
+    }
+
+    /// Restricts targets to a [`Direction`] from the main cursor
+    ///
+    /// With less targets, more of them get short labels.
+    pub fn with_direction(self, direction: Direction) -> Self {
+        Self { direction, ..self }
+    }
+
+    /// Changes the [`Action`] taken when a target is picked
+    pub fn with_action(self, action: Action) -> Self {
+        Self { action, ..self }
+    }
+
+    /// Calls a function on the picked target, instead of moving
+    ///
+    /// This is a shorthand for [`Action::Pick`], and lets you act on
+    /// a target without moving the cursor:
+    ///
+    /// ```rust
+    /// # use duat::prelude::*;
+    /// # use duat_hop::Hopper;
+    /// let hopper = Hopper::word().on_pick(|pa, buffer, range| {
+    ///     let line = buffer.read(pa).text().point_at_byte(range.start).line();
+    ///     context::info!("Picked a word on line [a]{line}");
+    /// });
+    /// ```
+    pub fn on_pick(
+        self,
+        on_pick: impl Fn(&mut Pass, &Handle, Range<usize>) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            action: Action::Pick(Arc::new(on_pick)),
+            ..self
+        }
+    }
+
+    /// Changes where in the target this [`Hopper`] lands
+    pub fn with_landing(self, landing: Landing) -> Self {
+        Self { landing, ..self }
+    }
+
+    /// Labels targets in every visible [`Buffer`], not just the
+    /// current one
+    ///
+    /// All [`Buffer`]s share the same labels, with the shortest ones
+    /// going to the current [`Buffer`]. Picking a target in another
+    /// [`Buffer`] will focus it.
+    pub fn across_windows(self) -> Self {
+        Self { across_windows: true, ..self }
+    }
+
+    /// Changes the keys that cancel this [`Hopper`]
+    ///
+    /// This overrides the keys set by [`Hop::with_cancel_keys`].
+    pub fn with_cancel_keys(self, keys: impl IntoIterator<Item = KeyEvent>) -> Self {
+        Self {
+            cancel_keys: Some(keys.into_iter().collect()),
+            ..self
+        }
+    }
+
+    /// Changes the key that confirms a [`Hopper::pattern`], or the
+    /// targets picked with [`Action::MultiPick`]
+    pub fn with_confirm_key(self, key: KeyEvent) -> Self {
+        Self { confirm_key: key, ..self }
+    }
+
+    /// The [`HopTarget`] to look for, if all input has been typed
+    fn hop_target(&self) -> Option<Arc<dyn HopTarget>> {
+        let literal = |typed: &str| -> Arc<dyn HopTarget> {
+            Arc::new(RegexTarget(regex_syntax::escape(typed).into()))
+        };
+
+        match &self.source {
+            Source::Target(target) => Some(target.clone()),
+            Source::Chars { len, typed } => (typed.chars().count() == *len).then(|| literal(typed)),
+            Source::Pattern { typed, .. } => (!typed.is_empty())
+                .then(|| Arc::new(RegexTarget(typed.as_str().into())) as Arc<dyn HopTarget>),
+        }
+    }
+
+    /// Finds the targets on screen, and labels them
+    ///
+    /// This replaces any previous targets, and can be called again
+    /// whenever the input changes. If there is still input to be
+    /// typed, no targets are found.
+    fn find_targets(&mut self, pa: &mut Pass) {
+        clear_targets(pa);
+        self.targets.clear();
+        self.picked.clear();
+
+        let Some(hop_target) = self.hop_target() else {
+            self.labels = Labels::default();
+            return;
+        };
+
+        let buffers = if self.across_windows {
+            visible_buffers(pa)
+        } else {
+            vec![context::current_buffer(pa)]
+        };
+
+        for buffer in buffers {
+            let (buf, area) = buffer.write_with_area(pa);
+
+            let opts = buf.print_opts();
+            let caret = buf.selections().get_main().unwrap().caret();
+            let mut text = buf.text_mut();
+
+            let id = form::id_of!("cloak");
+            text.insert_tag(*CLOAK_NS, .., id.to_tag(239));
+
+            let start = area.start_points(&text, opts).real;
+            let end = area.end_points(&text, opts).real;
+
+            let visible = start.byte()..end.byte();
+            let mut ranges = hop_target.targets(&text, visible.clone());
+            // A HopTarget could return ranges off screen, which can't
+            // be labeled. An empty match at the end of the Text is fine.
+            let at_end = |byte: usize| byte == visible.end && byte == text.len();
+            ranges.retain(|r| visible.contains(&r.start) || at_end(r.start));
+
+            let direction = self.direction;
+            ranges.retain(|r| direction.contains(&text, caret, r.start));
+
+            if let Some(order) = &self.order {
+                order.sort(&text, caret, &mut ranges);
+            }
+
+            if !matches!(self.source, Source::Target(_)) {
+                let id = form::id_of!("hop.match");
+                for r in ranges.iter() {
+                    text.insert_tag(*MATCH_NS, r.clone(), id.to_tag(240));
+                }
+            }
+
+            let targets = ranges
+                .into_iter()
+                .map(|range| Target { buffer: buffer.clone(), range });
+            self.targets.extend(targets);
+        }
+
+        let alphabet = self.alphabet.as_deref().unwrap_or_default();
+        self.labels = Labels::new(self.targets.len(), alphabet);
+
+        self.show_labels(pa);
+    }
+
+    /// Acts on a picked target, leaving this [`Mode`]
+    fn pick(&mut self, pa: &mut Pass, target: usize) {
+        let Target { buffer, range: r } = self.targets[target].clone();
+        let landed = self.landing.range(buffer.read(pa).text(), r.clone());
+
+        if let Action::Move | Action::Add | Action::Extend = self.action {
+            jumps::record(pa, &buffer);
+        }
+
+        match &self.action {
+            Action::Move => {
+                buffer.write(pa).remove_extra_selections();
+                buffer.edit_main(pa, |mut e| e.move_to(landed));
+            }
+            Action::Add => buffer.edit_main(pa, |mut e| e.copy().move_to(landed)),
+            Action::Extend => buffer.edit_main(pa, |mut e| {
+                e.set_anchor_if_needed();
+                if landed.start >= e.caret().byte() {
+                    e.move_to(landed.end);
+                    // An empty range has no last character to step back to.
+                    if landed.start < landed.end {
+                        e.move_hor(-1);
+                    }
+                } else {
+                    e.move_to(landed.start);
+                }
+            }),
+            Action::MultiPick => {
+                self.toggle_pick(pa, target);
+                return;
+            }
+            Action::Swap => {
+                let first = match self.picked.first() {
+                    Some(&first) if first != target => self.targets[first].clone(),
+                    _ => {
+                        self.toggle_pick(pa, target);
+                        return;
+                    }
+                };
+
+                exit(pa, self.return_to.as_ref());
+                if first.buffer != buffer {
+                    context::error!("Can't swap targets in different buffers");
+                } else if first.range.start < r.end && r.start < first.range.end {
+                    context::error!("Can't swap overlapping targets");
+                } else {
+                    swap(pa, &buffer, first.range, r);
+                }
+                return;
+            }
+            Action::Pick(on_pick) => {
+                let on_pick = on_pick.clone();
+                exit(pa, self.return_to.as_ref());
+                on_pick(pa, &buffer, landed);
+                return;
+            }
+        }
+
+        exit_to(pa, self.return_to.as_ref(), &buffer);
+    }
+
+    /// Toggles a target in or out of the picked targets
+    ///
+    /// Afterwards, every label is shown again, so more targets can
+    /// be picked.
+    fn toggle_pick(&mut self, pa: &mut Pass, target: usize) {
+        let Target { buffer, range } = &self.targets[target];
+
+        let mut text = buffer.text_mut(pa);
+        if let Some(i) = self.picked.iter().position(|picked| *picked == target) {
+            self.picked.remove(i);
+            text.remove_tags(*PICKED_NS, range.start);
+        } else {
+            self.picked.push(target);
+            let id = form::id_of!("hop.picked");
+            text.insert_tag(*PICKED_NS, range.clone(), id.to_tag(241));
+        }
+
+        self.labels.reset();
+        self.show_labels(pa);
+    }
+
+    /// Turns every picked target into a selection, leaving this
+    /// [`Mode`]
+    ///
+    /// If none of the picked targets are in the current [`Buffer`],
+    /// the [`Buffer`] of the first one is focused.
+    fn confirm_picks(&mut self, pa: &mut Pass) {
+        let current = context::current_buffer(pa);
+
+        let mut picked: Vec<Target> = self
+            .picked
+            .iter()
+            .map(|&target| {
+                let Target { buffer, range } = self.targets[target].clone();
+                let range = self.landing.range(buffer.read(pa).text(), range);
+                Target { buffer, range }
+            })
+            .collect();
+
+        let focus = match picked.first() {
+            Some(first) if picked.iter().all(|target| target.buffer != current) => {
+                first.buffer.clone()
+            }
+            _ => current,
+        };
+
+        while let Some(first) = picked.first() {
+            let buffer = first.buffer.clone();
+            let (in_buffer, others): (Vec<Target>, Vec<Target>) = picked
+                .into_iter()
+                .partition(|target| target.buffer == buffer);
+            picked = others;
+
+            let mut ranges: Vec<Range<usize>> =
+                in_buffer.into_iter().map(|target| target.range).collect();
+            ranges.sort_by_key(|r| r.start);
+
+            let mut ranges = ranges.into_iter();
+            let first = ranges.next().unwrap();
+
+            jumps::record(pa, &buffer);
+            buffer.write(pa).remove_extra_selections();
+            buffer.edit_main(pa, |mut e| e.move_to(first));
+            for r in ranges {
+                buffer.edit_main(pa, |mut e| e.copy().move_to(r));
+            }
+        }
+
+        exit_to(pa, self.return_to.as_ref(), &focus);
+    }
+
+    /// Shows the labels of every target that can still be picked
+    ///
+    /// The characters that have already been typed are left out.
+    fn show_labels(&self, pa: &mut Pass) {
+        for (target, overlay) in self.labels.overlays() {
+            let Target { buffer, range } = &self.targets[target];
+            let mut text = buffer.text_mut(pa);
+            text.remove_tags(*NS, range.start);
+            text.insert_tag(*NS, range.start, overlay);
+        }
     }
 }
 
 impl Mode for Hopper {
     fn bindings() -> mode::Bindings {
-        mode::bindings!(match _ {
+        let mut bindings = mode::bindings!(match _ {
             unmod!(KeyCode::Char(..)) => txt!("Filter hopping entries"),
-        })
+            unmod!(KeyCode::Backspace) => txt!("Undo the last label character"),
+            unmod!(KeyCode::Enter) => txt!("Confirm the pattern or picked targets"),
+        });
+        bindings
+            .list
+            .insert(2, (cancel_bindings(), txt!("Cancel hopping"), None));
+        bindings
     }
 
     fn send_key(&mut self, pa: &mut Pass, key_event: KeyEvent) {
+        if is_cancel(self.cancel_keys.as_deref().unwrap_or(&[]), key_event) {
+            if DEFAULTS.lock().unwrap().cancel_info {
+                context::info!("Hopping cancelled");
+            }
+            exit(pa, self.return_to.as_ref());
+            return;
+        }
+
+        if let Source::Pattern { typed, confirmed } = &mut self.source
+            && !*confirmed
+        {
+            if same_key(self.confirm_key, key_event) {
+                *confirmed = true;
+                return;
+            }
+
+            match key_event {
+                unmod!(KeyCode::Char(c)) => typed.push(c),
+                unmod!(KeyCode::Backspace) => {
+                    typed.pop();
+                }
+                _ => {
+                    context::error!("Invalid pattern input");
+                    exit(pa, self.return_to.as_ref());
+                    return;
+                }
+            }
+
+            // Keeping the previous targets until the regex is valid again.
+            if typed.is_empty() || "".try_search(typed.as_str()).is_ok() {
+                self.find_targets(pa);
+            }
+            return;
+        }
+
+        if let Action::MultiPick = self.action
+            && same_key(self.confirm_key, key_event)
+        {
+            self.confirm_picks(pa);
+            return;
+        }
+
         let char = match key_event {
             unmod!(KeyCode::Char(c)) => c,
+            unmod!(KeyCode::Backspace) => {
+                if self.labels.backspace() {
+                    self.show_labels(pa);
+                } else if !self.picked.is_empty() {
+                    // Searching again would throw away the picked targets.
+                    context::info!("Can't change the search after picking targets");
+                } else if let Source::Chars { typed, .. } = &mut self.source
+                    && typed.pop().is_some()
+                {
+                    self.find_targets(pa);
+                }
+                return;
+            }
             _ => {
                 context::error!("Invalid label input");
-                mode::reset::<Buffer>(pa);
+                exit(pa, self.return_to.as_ref());
                 return;
             }
         };
 
-        let buffer = context::current_buffer(pa);
+        if let Source::Chars { len, typed } = &mut self.source
+            && typed.chars().count() < *len
+        {
+            typed.push(char);
+            self.find_targets(pa);
+            return;
+        }
 
-        self.seq.push(char);
+        match self.labels.type_char(char) {
+            Typed::Invalid => exit(pa, self.return_to.as_ref()),
+            Typed::Target(target) => self.pick(pa, target),
+            Typed::Prefix(dropped) => {
+                for target in dropped {
+                    let Target { buffer, range } = &self.targets[target];
+                    // Labels are overlays on the start of their targets.
+                    buffer.text_mut(pa).remove_tags(*NS, range.start);
+                }
+                self.show_labels(pa);
+            }
+        }
+    }
+}
 
-        buffer.write(pa).remove_extra_selections();
+/// What a [`Hopper`] does with the target that was picked
+#[derive(Clone, Default)]
+pub enum Action {
+    /// Moves the main selection to the target, removing the others
+    #[default]
+    Move,
+    /// Adds a new selection on the target, keeping the others
+    ///
+    /// This lets you build up selections across the screen with
+    /// repeated hops.
+    Add,
+    /// Extends the main selection to the target, keeping its anchor
+    ///
+    /// The caret is moved to the end of the target if it comes after
+    /// the caret, and to its start otherwise, so the selection grows
+    /// to cover the whole target. If a [`Landing`] other than
+    /// [`Landing::Whole`] is used, the caret is moved to it instead.
+    Extend,
+    /// Toggles targets into a set of picked targets, which are turned
+    /// into selections once the confirm key is pressed
+    ///
+    /// Labels stay on screen after each pick, and picked targets are
+    /// shown with the `"hop.picked"` [`Form`].
+    MultiPick,
+    /// Calls a function on the picked target, without moving the
+    /// cursor
+    ///
+    /// The function receives the [`Buffer`]'s [`Handle`] and the
+    /// range of the target, after applying the [`Landing`].
+    Pick(Arc<dyn Fn(&mut Pass, &Handle, Range<usize>) + Send + Sync>),
+    /// Swaps the text of two targets, picked one after the other
+    ///
+    /// The first target is shown with the `"hop.picked"` [`Form`]
+    /// while the second one is being picked. Both targets are
+    /// swapped in one edit, so a single undo reverts the swap.
+    Swap,
+}
 
-        let seqs = key_seqs(self.ranges.len());
-        for (seq, r) in seqs.iter().zip(&self.ranges) {
-            if *seq == self.seq {
-                buffer.edit_main(pa, |mut e| e.move_to(r.clone()));
-                mode::reset::<Buffer>(pa);
-            } else if seq.starts_with(&self.seq) {
-                continue;
-            }
-            // Removing one end of the conceal range will remove both ends.
-            buffer.write(pa).text_mut().remove_tags(*NS, r.start);
+impl Action {
+    /// Yanks the picked target into the clipboard, without moving
+    /// the cursor
+    pub fn yank() -> Self {
+        Self::Pick(Arc::new(|pa, buffer, range| {
+            let yanked = buffer.edit_main(pa, |mut e| {
+                let mut c = e.copy();
+                c.move_to(range);
+                let yanked = c.selection().to_string();
+                c.destroy();
+                yanked
+            });
+
+            duat::clipboard::set(yanked);
+        }))
+    }
+
+    /// Deletes the picked target, without moving the cursor
+    pub fn delete() -> Self {
+        Self::Pick(Arc::new(|pa, buffer, range| {
+            buffer.edit_main(pa, |mut e| {
+                let mut c = e.copy();
+                c.move_to(range);
+                c.replace("");
+                c.destroy();
+            });
+        }))
+    }
+}
+
+/// Where in a target a [`Hopper`] lands
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Landing {
+    /// Selects the whole target
+    #[default]
+    Whole,
+    /// Lands on the first character of the target
+    First,
+    /// Lands on the last character of the target
+    Last,
+    /// Lands on a character offset from the start of the target
+    ///
+    /// The offset is clamped to the last character of the target,
+    /// like `hop.nvim`'s `hint_offset`.
+    Offset(usize),
+}
+
+impl Landing {
+    /// The byte range that should be selected when landing on `r`
+    ///
+    /// A target at the end of the [`Text`] has no characters to land
+    /// on, so it gets an empty range there.
+    fn range(&self, text: &Text, r: Range<usize>) -> Range<usize> {
+        if r.start >= text.len() {
+            return text.len()..text.len();
+        }
+
+        let start = text.point_at_byte(r.start).char();
+        let last = text
+            .point_at_byte(r.end.min(text.len()))
+            .char()
+            .saturating_sub(1)
+            .max(start);
+
+        let char = match self {
+            Landing::Whole => return r,
+            Landing::First => start,
+            Landing::Last => last,
+            Landing::Offset(offset) => (start + offset).min(last),
+        };
+
+        let next = (char + 1).min(text.end_point().char());
+        text.point_at_char(char).byte()..text.point_at_char(next).byte()
+    }
+}
+
+/// Where targets are allowed to be, relative to the main cursor
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Anywhere on screen
+    #[default]
+    Both,
+    /// Only before the main cursor
+    Before,
+    /// Only after the main cursor
+    After,
+    /// Only on the line of the main cursor
+    Line,
+}
+
+impl Direction {
+    /// Whether a target starting at `byte` is in this [`Direction`]
+    fn contains(&self, text: &Text, caret: Point, byte: usize) -> bool {
+        match self {
+            Direction::Both => true,
+            Direction::Before => byte < caret.byte(),
+            Direction::After => byte > caret.byte(),
+            Direction::Line => text.point_at_byte(byte).line() == caret.line(),
+        }
+    }
+}
+
+/// A target on screen, in one of the visible [`Buffer`]s
+#[derive(Clone)]
+struct Target {
+    buffer: Handle,
+    range: Range<usize>,
+}
+
+/// Returns to the [`Mode`] that hopping started from
+type ReturnTo = Arc<dyn Fn(&mut Pass) + Send + Sync>;
+
+/// Where the targets of a [`Hopper`] come from
+#[derive(Clone)]
+enum Source {
+    /// A fixed [`HopTarget`]
+    Target(Arc<dyn HopTarget>),
+    /// Characters that are typed before labels are shown
+    Chars { len: usize, typed: String },
+    /// A pattern that is typed while labels are shown
+    Pattern { typed: String, confirmed: bool },
+}
+
+/// The order in which labels are handed out to targets
+///
+/// Targets that come first get the shortest labels.
+#[derive(Clone, Default)]
+pub enum Order {
+    /// In the order that they show up on screen
+    #[default]
+    Text,
+    /// By distance in bytes from the main caret
+    Bytes,
+    /// By distance in lines from the main caret, then by distance in
+    /// columns
+    Lines,
+    /// By a custom distance from the main caret
+    ///
+    /// The function takes the [`Text`], the main caret, and the range
+    /// of a target, returning its distance.
+    Custom(Arc<dyn Fn(&Text, Point, Range<usize>) -> usize + Send + Sync>),
+}
+
+impl Order {
+    /// Sorts the targets, so the first ones get the shortest labels
+    fn sort(&self, text: &Text, caret: Point, ranges: &mut [Range<usize>]) {
+        let col = |p: Point| p.char() - text.point_at_coords(p.line(), 0).char();
+
+        match self {
+            Order::Text => {}
+            Order::Bytes => ranges.sort_by_key(|r| r.start.abs_diff(caret.byte())),
+            Order::Lines => ranges.sort_by_cached_key(|r| {
+                let p = text.point_at_byte(r.start);
+                (p.line().abs_diff(caret.line()), col(p).abs_diff(col(caret)))
+            }),
+            Order::Custom(dist) => ranges.sort_by_cached_key(|r| dist(text, caret, r.clone())),
+        }
+    }
+}
+
+/// Hops again, with the last [`Hopper`] that was used
+///
+/// The [`Hopper`] keeps its target, direction, [`Action`] and every
+/// other option, but any input typed for it has to be typed again.
+pub fn repeat_hop(pa: &mut Pass) {
+    let last = LAST.lock().unwrap().clone();
+    match last {
+        Some(hopper) => mode::set(pa, hopper),
+        None => context::info!("There is no [mode]hop[] to repeat"),
+    }
+}
+
+/// Swaps the text of two non overlapping ranges
+fn swap(pa: &mut Pass, buffer: &Handle, lhs: Range<usize>, rhs: Range<usize>) {
+    let (first, second) = if lhs.start < rhs.start {
+        (lhs, rhs)
+    } else {
+        (rhs, lhs)
+    };
+
+    buffer.edit_main(pa, |mut e| {
+        let mut c = e.copy();
+        c.move_to(first.clone());
+        let first_str = c.selection().to_string();
+        c.move_to(second);
+        let second_str = c.selection().to_string();
+
+        // Replacing the second range first keeps the first one valid.
+        c.replace(first_str);
+        c.move_to(first);
+        c.replace(second_str);
+        c.destroy();
+    });
+}
+
+/// The keys of the default maps of [`Hop`]
+///
+/// A map is left out if its keys are [`None`].
+#[derive(Clone, Copy, Debug)]
+pub struct DefaultKeys {
+    /// The keys for [`Hopper::word`], `w` by default
+    pub word: Option<&'static str>,
+    /// The keys for [`Hopper::line`], `l` by default
+    pub line: Option<&'static str>,
+    /// The keys for [`jump_back`], `o` by default
+    pub jump_back: Option<&'static str>,
+    /// The keys for [`jump_forward`], `i` by default
+    pub jump_forward: Option<&'static str>,
+    /// The keys for [`repeat_hop`], `.` by default
+    pub repeat_hop: Option<&'static str>,
+    /// The keys for the [`WindowPicker`], `W` by default
+    pub window_picker: Option<&'static str>,
+}
+
+impl Default for DefaultKeys {
+    fn default() -> Self {
+        Self {
+            word: Some("w"),
+            line: Some("l"),
+            jump_back: Some("o"),
+            jump_forward: Some("i"),
+            repeat_hop: Some("."),
+            window_picker: Some("W"),
+        }
+    }
+}
+
+/// Something that keys can be mapped to through [`Hop::map`]
+///
+/// This is implemented for [`Hopper`]s, the [`WindowPicker`], and
+/// functions like [`jump_back`], [`jump_forward`] and
+/// [`repeat_hop`].
+pub trait MapsToHop: Send + 'static {
+    /// Runs this when the mapped keys are typed
+    fn run(&mut self, pa: &mut Pass);
+}
+
+impl<F: FnMut(&mut Pass) + Send + 'static> MapsToHop for F {
+    fn run(&mut self, pa: &mut Pass) {
+        self(pa)
+    }
+}
+
+impl MapsToHop for Hopper {
+    fn run(&mut self, pa: &mut Pass) {
+        mode::set(pa, self.clone());
+    }
+}
+
+impl MapsToHop for WindowPicker {
+    fn run(&mut self, pa: &mut Pass) {
+        mode::set(pa, self.clone());
+    }
+}
+
+/// Maps the default keys in the [`Mode`] `M`
+fn map_defaults<M: Mode>(keys: DefaultKeys) {
+    if let Some(keys) = keys.word {
+        mode::map::<M>(keys, |pa: &mut Pass| mode::set(pa, Hopper::word()))
+            .doc(txt!("[mode]Hop[] to a [a]word"));
+    }
+    if let Some(keys) = keys.line {
+        mode::map::<M>(keys, |pa: &mut Pass| mode::set(pa, Hopper::line()))
+            .doc(txt!("[mode]Hop[] to a [a]line"));
+    }
+    if let Some(keys) = keys.jump_back {
+        mode::map::<M>(keys, jump_back).doc(txt!("Jump back from a [mode]hop"));
+    }
+    if let Some(keys) = keys.jump_forward {
+        mode::map::<M>(keys, jump_forward).doc(txt!("Jump forward to a [mode]hop"));
+    }
+    if let Some(keys) = keys.repeat_hop {
+        mode::map::<M>(keys, repeat_hop).doc(txt!("Repeat the last [mode]hop"));
+    }
+    if let Some(keys) = keys.window_picker {
+        mode::map::<M>(keys, |pa: &mut Pass| mode::set(pa, WindowPicker::new()))
+            .doc(txt!("Pick a [a]window[] to focus"));
+    }
+}
+
+/// Makes [`Hopper`]s and [`WindowPicker`]s return to `M` if they
+/// were entered from it
+fn add_return_hook<M: Mode + Clone>() {
+    hook::add::<ModeSwitched>(|_, mut switch| {
+        let Some(old) = switch.old.get_as::<M>().cloned() else {
+            return;
+        };
+
+        let old = Mutex::new(old);
+        let return_to: ReturnTo = Arc::new(move |pa| mode::set(pa, old.lock().unwrap().clone()));
+
+        if let Some(hop) = switch.new.get_as::<Hopper>() {
+            hop.return_to = Some(return_to);
+        } else if let Some(picker) = switch.new.get_as::<WindowPicker>() {
+            picker.return_to = Some(return_to);
         }
+    });
+}
 
-        if self.seq.chars().count() == 2 || !LETTERS.contains(char) {
-            mode::reset::<Buffer>(pa);
+/// Leaves a hopping [`Mode`], returning to the previous one if
+/// possible
+fn exit(pa: &mut Pass, return_to: Option<&ReturnTo>) {
+    match return_to {
+        Some(return_to) => return_to(pa),
+        None => mode::reset::<Buffer>(pa),
+    }
+}
+
+/// Leaves a hopping [`Mode`], focusing `buffer` if it isn't the
+/// current [`Buffer`]
+///
+/// The previous [`Mode`] is returned to on the focused [`Buffer`].
+fn exit_to(pa: &mut Pass, return_to: Option<&ReturnTo>, buffer: &Handle) {
+    if *buffer == context::current_buffer(pa) {
+        exit(pa, return_to);
+    } else {
+        mode::reset_to(pa, &buffer.to_dyn());
+        if let Some(return_to) = return_to {
+            return_to(pa);
+        }
+    }
+}
+
+/// Removes all labels and cloaking from every visible [`Buffer`]
+fn clear_targets(pa: &mut Pass) {
+    for buffer in visible_buffers(pa) {
+        let mut text = buffer.text_mut(pa);
+        text.remove_tags(*NS, ..);
+        text.remove_tags(*MATCH_NS, ..);
+        text.remove_tags(*PICKED_NS, ..);
+        text.remove_tags(*CLOAK_NS, ..);
+    }
+}
+
+/// Every [`Buffer`] in the current window, starting with the current
+/// one
+fn visible_buffers(pa: &Pass) -> Vec<Handle> {
+    let current = context::current_buffer(pa);
+
+    let window = context::current_window(pa);
+    let others = window
+        .buffers(pa)
+        .into_iter()
+        .filter(|buffer| *buffer != current);
+
+    let mut buffers = vec![current.clone()];
+    buffers.extend(others);
+    buffers
+}
+
+/// Whether two [`KeyEvent`]s are the same key press
+fn same_key(lhs: KeyEvent, rhs: KeyEvent) -> bool {
+    lhs.code == rhs.code && lhs.modifiers == rhs.modifiers
+}
+
+/// The [`Binding`]s of the default cancel keys
+///
+/// [`Binding`]: mode::Binding
+fn cancel_bindings() -> Vec<mode::Binding> {
+    let defaults = DEFAULTS.lock().unwrap();
+    let keys = defaults.cancel_keys.iter();
+    keys.map(|key| mode::Binding::new(key.code, key.modifiers))
+        .collect()
+}
+
+/// Warns about cancel keys that are also characters of an alphabet
+///
+/// Since cancel keys are checked first, those characters could never
+/// be typed in a label.
+fn check_cancel_keys(alphabet: &[char], cancel_keys: &[KeyEvent]) {
+    for key in cancel_keys {
+        if let KeyCode::Char(char) = key.code
+            && key.modifiers == mode::KeyMod::NONE
+            && alphabet.contains(&char)
+        {
+            context::warn!("Cancel key {char} is also in the hop alphabet, so it can't be typed");
         }
     }
 }
 
-fn key_seqs(len: usize) -> Vec<String> {
-    let double = len / LETTERS.len();
-    let mut seqs = Vec::new();
+/// Builds an alphabet out of a `&str`, without repeated characters
+fn new_alphabet(alphabet: &str) -> Arc<[char]> {
+    let mut chars: Vec<char> = Vec::new();
+    for char in alphabet.chars() {
+        if !chars.contains(&char) {
+            chars.push(char);
+        }
+    }
 
-    seqs.extend(LETTERS.chars().skip(double).map(char::into));
-    let chars = LETTERS.chars().take(double);
-    seqs.extend(chars.flat_map(|c1| LETTERS.chars().map(move |c2| format!("{c1}{c2}"))));
+    assert!(
+        chars.len() >= 2,
+        "A hop alphabet needs at least 2 distinct characters"
+    );
+
+    chars.into()
+}
 
-    seqs
+/// Defaults for [`Hopper`]s, set when plugging [`Hop`]
+struct Defaults {
+    alphabet: Arc<[char]>,
+    order: Order,
+    cancel_keys: Arc<[KeyEvent]>,
+    cancel_info: bool,
 }
 
 static LETTERS: &str = "abcdefghijklmnopqrstuvwxyz";
+static DEFAULTS: LazyLock<Mutex<Defaults>> = LazyLock::new(|| {
+    Mutex::new(Defaults {
+        alphabet: LETTERS.chars().collect(),
+        order: Order::Text,
+        cancel_keys: Arc::new([KeyEvent::from(KeyCode::Esc)]),
+        cancel_info: false,
+    })
+});
+static LAST: Mutex<Option<Hopper>> = Mutex::new(None);
 static NS: LazyLock<Ns> = Ns::new_lazy();
+static MATCH_NS: LazyLock<Ns> = Ns::new_lazy();
+static PICKED_NS: LazyLock<Ns> = Ns::new_lazy();
+static CLOAK_NS: LazyLock<Ns> = Ns::new_lazy();
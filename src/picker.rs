@@ -0,0 +1,186 @@
+//! A [`Mode`] to pick one of the visible [`Buffer`]s by its label
+//!
+//! This reuses the label trie of the [`Hopper`], but labels the
+//! areas of [`Buffer`]s instead of ranges of text.
+//!
+//! [`Hopper`]: crate::Hopper
+use std::sync::{Arc, LazyLock};
+
+use duat::prelude::*;
+
+use crate::{
+    DEFAULTS, ReturnTo, cancel_bindings, check_cancel_keys, exit, exit_to,
+    labels::{Labels, Typed, is_cancel},
+    new_alphabet, visible_buffers,
+};
+
+/// A [`Mode`] to focus one of the visible [`Buffer`]s
+///
+/// Each visible [`Buffer`] is cloaked, and gets a label in the
+/// middle of its area, on a band as wide as the area and three lines
+/// tall. Typing that label focuses it. The labels use the same
+/// alphabet and cancel keys as [`Hopper`]s, and the previous
+/// [`Mode`] is only returned to if it was registered with
+/// [`Hop::return_to`].
+///
+/// Only the areas of [`Buffer`]s are labeled, so other widgets, like
+/// the status line, can't be picked.
+///
+/// [`Hopper`]: crate::Hopper
+/// [`Hop::return_to`]: crate::Hop::return_to
+#[derive(Clone, Default)]
+pub struct WindowPicker {
+    alphabet: Option<Arc<[char]>>,
+    pub(crate) return_to: Option<ReturnTo>,
+    buffers: Vec<Handle>,
+    labels: Labels,
+}
+
+impl WindowPicker {
+    /// Returns a new [`WindowPicker`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Changes the characters used on labels, overriding the default
+    /// set through [`Hop::with_alphabet`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are less than two distinct characters.
+    ///
+    /// [`Hop::with_alphabet`]: crate::Hop::with_alphabet
+    pub fn with_alphabet(self, alphabet: &str) -> Self {
+        Self {
+            alphabet: Some(new_alphabet(alphabet)),
+            ..self
+        }
+    }
+
+    /// Labels every visible [`Buffer`]
+    pub(crate) fn label_buffers(&mut self, pa: &mut Pass) {
+        let defaults = DEFAULTS.lock().unwrap();
+        if let Some(alphabet) = &self.alphabet {
+            check_cancel_keys(alphabet, &defaults.cancel_keys);
+        }
+        let alphabet = self
+            .alphabet
+            .get_or_insert_with(|| defaults.alphabet.clone());
+        drop(defaults);
+
+        self.buffers = visible_buffers(pa);
+        self.labels = Labels::new(self.buffers.len(), alphabet);
+
+        self.show_labels(pa);
+    }
+
+    /// Shows the labels of every [`Buffer`] that can still be picked
+    ///
+    /// The characters that have already been typed are left out, and
+    /// the [`Buffer`]s that can't be picked anymore lose their label.
+    fn show_labels(&self, pa: &mut Pass) {
+        let targets = self.labels.targets();
+
+        for (target, buffer) in self.buffers.iter().enumerate() {
+            let (buf, area) = buffer.write_with_area(pa);
+            let opts = buf.print_opts();
+            let mut text = buf.text_mut();
+            text.remove_tags(*PICKER_NS, ..);
+
+            let id = form::id_of!("cloak");
+            text.insert_tag(*PICKER_NS, .., id.to_tag(239));
+
+            if !targets.contains(&target) {
+                continue;
+            }
+
+            let start = area.start_points(&text, opts).real;
+            let end = area.end_points(&text, opts).real;
+
+            // Overlays go on the start of lines, so only lines that
+            // start on screen can hold a row of the label.
+            let last_line = end.line().min(text.end_point().line());
+            let mut rows: Vec<usize> = (start.line()..=last_line)
+                .map(|line| text.point_at_coords(line, 0).byte())
+                .filter(|byte| (start.byte()..end.byte()).contains(byte))
+                .collect();
+            if rows.is_empty() {
+                rows.push(start.byte());
+            }
+
+            let width = area.width() as usize;
+            let label = self.labels.seq(target);
+            let mid = rows.len() / 2;
+
+            for (i, &byte) in rows.iter().enumerate() {
+                let row = match i.abs_diff(mid) {
+                    0 => format!("{label:^width$}"),
+                    1 => " ".repeat(width),
+                    _ => continue,
+                };
+                let overlay = Overlay::new(txt!("[hop.window:240]{row}"));
+                text.insert_tag(*PICKER_NS, byte, overlay);
+            }
+        }
+    }
+}
+
+impl Mode for WindowPicker {
+    fn bindings() -> mode::Bindings {
+        let mut bindings = mode::bindings!(match _ {
+            unmod!(KeyCode::Char(..)) => txt!("Pick a window by its label"),
+            unmod!(KeyCode::Backspace) => txt!("Undo the last label character"),
+        });
+        bindings
+            .list
+            .push((cancel_bindings(), txt!("Cancel picking"), None));
+        bindings
+    }
+
+    fn send_key(&mut self, pa: &mut Pass, key_event: KeyEvent) {
+        let defaults = DEFAULTS.lock().unwrap();
+        let cancelled = is_cancel(&defaults.cancel_keys, key_event);
+        let cancel_info = defaults.cancel_info;
+        drop(defaults);
+
+        if cancelled {
+            if cancel_info {
+                context::info!("Window picking cancelled");
+            }
+            exit(pa, self.return_to.as_ref());
+            return;
+        }
+
+        let char = match key_event {
+            unmod!(KeyCode::Char(c)) => c,
+            unmod!(KeyCode::Backspace) => {
+                if self.labels.backspace() {
+                    self.show_labels(pa);
+                }
+                return;
+            }
+            _ => {
+                context::error!("Invalid label input");
+                exit(pa, self.return_to.as_ref());
+                return;
+            }
+        };
+
+        match self.labels.type_char(char) {
+            Typed::Invalid => exit(pa, self.return_to.as_ref()),
+            Typed::Target(target) => {
+                exit_to(pa, self.return_to.as_ref(), &self.buffers[target]);
+            }
+            Typed::Prefix(_) => self.show_labels(pa),
+        }
+    }
+}
+
+/// Removes the labels and cloaking from every visible [`Buffer`]
+pub(crate) fn clear_labels(pa: &mut Pass) {
+    for buffer in visible_buffers(pa) {
+        buffer.text_mut(pa).remove_tags(*PICKER_NS, ..);
+    }
+}
+
+static PICKER_NS: LazyLock<Ns> = Ns::new_lazy();
@@ -0,0 +1,59 @@
+//! Providers of targets for the [`Hopper`]
+//!
+//! [`Hopper`]: crate::Hopper
+use std::{ops::Range, sync::Arc};
+
+use duat::text::{RegexHaystack, Text};
+
+/// A provider of targets for a [`Hopper`]
+///
+/// Implementing this trait lets you hop to anything you can find in
+/// the [`Text`], be it syntax nodes, diagnostics, or whatever else.
+/// It is also implemented for functions with the same signature as
+/// [`HopTarget::targets`].
+///
+/// [`Hopper`]: crate::Hopper
+pub trait HopTarget: Send + Sync + 'static {
+    /// The byte ranges of every target within `range`
+    ///
+    /// `range` is the byte range of the part of the [`Text`] that is
+    /// visible on screen. Each returned range will get a label at its
+    /// start, and will be selected when hopped to.
+    fn targets(&self, text: &Text, range: Range<usize>) -> Vec<Range<usize>>;
+}
+
+impl<F> HopTarget for F
+where
+    F: Fn(&Text, Range<usize>) -> Vec<Range<usize>> + Send + Sync + 'static,
+{
+    fn targets(&self, text: &Text, range: Range<usize>) -> Vec<Range<usize>> {
+        self(text, range)
+    }
+}
+
+/// A [`HopTarget`] for every match of a regex
+#[derive(Clone)]
+pub struct RegexTarget(pub(crate) Arc<str>);
+
+impl RegexTarget {
+    /// Returns a new [`RegexTarget`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the regex is not valid, as searched for by
+    /// duat.
+    pub fn new(regex: impl Into<Arc<str>>) -> Result<Self, Box<regex_syntax::Error>> {
+        let regex = regex.into();
+        "".try_search(&*regex)?;
+        Ok(Self(regex))
+    }
+}
+
+impl HopTarget for RegexTarget {
+    fn targets(&self, text: &Text, range: Range<usize>) -> Vec<Range<usize>> {
+        match text.try_search(&*self.0) {
+            Ok(matches) => matches.range(range).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}